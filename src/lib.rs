@@ -3,9 +3,11 @@ pub mod cli;
 use std::{
     collections::HashMap,
     convert::From,
-    env, fmt,
+    env, fmt, fs,
+    io::{BufRead, BufReader},
     num::ParseIntError,
     path::{Path, PathBuf},
+    process::{Command, Stdio},
     str::FromStr,
 };
 
@@ -17,6 +19,10 @@ pub enum Error {
     ParseVersionComponentError(ParseIntError),
     // RequestedVersion::from_str
     DotMissing,
+    // RequestedVersion::from_str (version ranges)
+    UnknownRangeOperator,
+    CompatibleReleaseMissingMinor,
+    CompatibleReleaseMajorOverflow,
     // ExactVersion::from_path
     FileNameMissing,
     FileNameToStrError,
@@ -32,6 +38,16 @@ impl fmt::Display for Error {
                 write!(f, "Error parsing a version component: {}", int_error)
             }
             Self::DotMissing => write!(f, "'.' missing from the version"),
+            Self::UnknownRangeOperator => write!(
+                f,
+                "Unrecognized operator in a version range (expected one of >=, >, <=, <, ==, !=, ~=)"
+            ),
+            Self::CompatibleReleaseMissingMinor => {
+                write!(f, "'~=' requires a minor version (e.g. '~=3.9')")
+            }
+            Self::CompatibleReleaseMajorOverflow => {
+                write!(f, "'~=' major version is too large to expand into an upper bound")
+            }
             Self::FileNameMissing => write!(f, "Path lacks a file name"),
             Self::FileNameToStrError => write!(f, "Failed to convert file name to `str`"),
             Self::PathFileNameError => write!(f, "File name not of the format `pythonX.Y`"),
@@ -49,6 +65,9 @@ impl std::error::Error for Error {
         match self {
             Self::ParseVersionComponentError(int_error) => Some(int_error),
             Self::DotMissing => None,
+            Self::UnknownRangeOperator => None,
+            Self::CompatibleReleaseMissingMinor => None,
+            Self::CompatibleReleaseMajorOverflow => None,
             Self::FileNameMissing => None,
             Self::FileNameToStrError => None,
             Self::PathFileNameError => None,
@@ -60,20 +79,258 @@ impl std::error::Error for Error {
 /// An integral part of a version specifier (e.g. the `X` or `Y` of `X.Y`).
 type ComponentSize = u16;
 
-/// Represents the version of Python a user requsted.
+/// A Python implementation that can be requested or discovered on `PATH`
+/// (e.g. the `pypy` of `pypy3.9`).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Implementation {
+    CPython,
+    PyPy,
+    GraalPy,
+    /// Any other `<prefix>X.Y`-named implementation not known above.
+    Other(String),
+}
+
+impl Implementation {
+    fn from_prefix(prefix: &str) -> Self {
+        match prefix {
+            "python" => Self::CPython,
+            "pypy" => Self::PyPy,
+            "graalpy" => Self::GraalPy,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    fn display_name(&self) -> &str {
+        match self {
+            Self::CPython => "Python",
+            Self::PyPy => "PyPy",
+            Self::GraalPy => "GraalPy",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+/// Implementation prefixes recognized by name alone (i.e. without needing to
+/// probe the executable), shared by `RequestedVersion::from_interpreter_name`
+/// and `probe_unversioned_executables`'s candidate filter.
+const KNOWN_IMPLEMENTATION_PREFIXES: &[&str] = &["python", "pypy", "graalpy"];
+
+/// A comparison operator used in a PEP 440-style version specifier.
 #[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+    /// `~=`; expanded into a `GreaterThanOrEqual`/`LessThan` pair while parsing.
+    Compatible,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::GreaterThan => ">",
+            Self::GreaterThanOrEqual => ">=",
+            Self::LessThan => "<",
+            Self::LessThanOrEqual => "<=",
+            Self::Compatible => "~=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// A single clause of a version range, e.g. the `>=3.10` of `>=3.10,<3.13`.
+///
+/// A `minor` of `None` means the clause only constrains the major version
+/// (as with a bare `<4` or the PEP 440 wildcard `==3.*`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clause {
+    pub operator: Operator,
+    pub major: ComponentSize,
+    pub minor: Option<ComponentSize>,
+}
+
+impl fmt::Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.operator, self.major)?;
+        if let Some(minor) = self.minor {
+            write!(f, ".{}", minor)?;
+        }
+        Ok(())
+    }
+}
+
+impl Clause {
+    /// Whether `major.minor` satisfies this clause.
+    fn is_satisfied_by(&self, major: ComponentSize, minor: ComponentSize) -> bool {
+        match self.minor {
+            Some(clause_minor) => {
+                let requested = (self.major, clause_minor);
+                let actual = (major, minor);
+                match self.operator {
+                    Operator::Equal => actual == requested,
+                    Operator::NotEqual => actual != requested,
+                    Operator::GreaterThan => actual > requested,
+                    Operator::GreaterThanOrEqual => actual >= requested,
+                    Operator::LessThan => actual < requested,
+                    Operator::LessThanOrEqual => actual <= requested,
+                    Operator::Compatible => unreachable!("'~=' is expanded while parsing"),
+                }
+            }
+            None => match self.operator {
+                Operator::Equal => major == self.major,
+                Operator::NotEqual => major != self.major,
+                Operator::GreaterThan => major > self.major,
+                Operator::GreaterThanOrEqual => major >= self.major,
+                Operator::LessThan => major < self.major,
+                Operator::LessThanOrEqual => major <= self.major,
+                Operator::Compatible => unreachable!("'~=' is expanded while parsing"),
+            },
+        }
+    }
+}
+
+// Recognized operator prefixes, longest first so e.g. `>=` isn't parsed as `>` followed by `=`.
+const OPERATORS: [(&str, Operator); 7] = [
+    (">=", Operator::GreaterThanOrEqual),
+    ("<=", Operator::LessThanOrEqual),
+    ("==", Operator::Equal),
+    ("!=", Operator::NotEqual),
+    ("~=", Operator::Compatible),
+    (">", Operator::GreaterThan),
+    ("<", Operator::LessThan),
+];
+
+fn parse_clause_version(version_string: &str) -> Result<(ComponentSize, Option<ComponentSize>)> {
+    if let Some(dot_index) = version_string.find('.') {
+        let major_str = &version_string[..dot_index];
+        let major = major_str
+            .parse::<ComponentSize>()
+            .map_err(Error::ParseVersionComponentError)?;
+
+        let minor_str = &version_string[dot_index + 1..];
+        if minor_str == "*" {
+            Ok((major, None))
+        } else {
+            let minor = minor_str
+                .parse::<ComponentSize>()
+                .map_err(Error::ParseVersionComponentError)?;
+            Ok((major, Some(minor)))
+        }
+    } else {
+        let major = version_string
+            .parse::<ComponentSize>()
+            .map_err(Error::ParseVersionComponentError)?;
+        Ok((major, None))
+    }
+}
+
+/// Parses a single comma-separated piece of a version range (e.g. `>=3.10`)
+/// into one or more `Clause`s (`~=` expands into two).
+fn parse_clause(raw: &str) -> Result<Vec<Clause>> {
+    let raw = raw.trim();
+    let (operator, rest) = OPERATORS
+        .iter()
+        .find_map(|(symbol, operator)| raw.strip_prefix(symbol).map(|rest| (*operator, rest)))
+        .ok_or(Error::UnknownRangeOperator)?;
+
+    let (major, minor) = parse_clause_version(rest)?;
+
+    if let Operator::Compatible = operator {
+        let minor = minor.ok_or(Error::CompatibleReleaseMissingMinor)?;
+        Ok(vec![
+            Clause {
+                operator: Operator::GreaterThanOrEqual,
+                major,
+                minor: Some(minor),
+            },
+            Clause {
+                operator: Operator::LessThan,
+                major: major
+                    .checked_add(1)
+                    .ok_or(Error::CompatibleReleaseMajorOverflow)?,
+                minor: None,
+            },
+        ])
+    } else {
+        Ok(vec![Clause {
+            operator,
+            major,
+            minor,
+        }])
+    }
+}
+
+fn parse_range(version_string: &str) -> Result<Vec<Clause>> {
+    version_string
+        .split(',')
+        .map(parse_clause)
+        .collect::<Result<Vec<Vec<Clause>>>>()
+        .map(|clauses| clauses.into_iter().flatten().collect())
+}
+
+/// Splits a leading run of ASCII letters (an implementation prefix, e.g. `pypy`)
+/// off the front of a version request, e.g. `pypy3.9` -> (`pypy`, `3.9`).
+fn split_implementation_prefix(version_string: &str) -> (Option<&str>, &str) {
+    match version_string.find(|c: char| !c.is_ascii_alphabetic()) {
+        Some(0) | None => (None, version_string),
+        Some(split_at) => (
+            Some(&version_string[..split_at]),
+            &version_string[split_at..],
+        ),
+    }
+}
+
+fn parse_major_minor(version_string: &str) -> Result<(ComponentSize, ComponentSize)> {
+    if let Some(dot_index) = version_string.find('.') {
+        let major_str = &version_string[..dot_index];
+        let major = match major_str.parse::<ComponentSize>() {
+            Ok(number) => number,
+            Err(parse_error) => return Err(Error::ParseVersionComponentError(parse_error)),
+        };
+
+        let minor_str = &version_string[dot_index + 1..];
+        match minor_str.parse::<ComponentSize>() {
+            Ok(minor) => Ok((major, minor)),
+            Err(parse_error) => Err(Error::ParseVersionComponentError(parse_error)),
+        }
+    } else {
+        Err(Error::DotMissing)
+    }
+}
+
+/// Represents the version of Python a user requsted.
+#[derive(Clone, Debug, PartialEq)]
 pub enum RequestedVersion {
     Any,
-    MajorOnly(ComponentSize),
-    Exact(ComponentSize, ComponentSize),
+    MajorOnly(Implementation, ComponentSize),
+    Exact(Implementation, ComponentSize, ComponentSize),
+    /// A PEP 440-style version range, e.g. `>=3.10,<3.13` or `~=3.9`.
+    Range(Implementation, Vec<Clause>),
 }
 
 impl ToString for RequestedVersion {
     fn to_string(&self) -> String {
         match self {
             Self::Any => "Python".to_string(),
-            Self::MajorOnly(major) => format!("Python {}", major),
-            Self::Exact(major, minor) => format!("Python {}.{}", major, minor),
+            Self::MajorOnly(implementation, major) => {
+                format!("{} {}", implementation.display_name(), major)
+            }
+            Self::Exact(implementation, major, minor) => {
+                format!("{} {}.{}", implementation.display_name(), major, minor)
+            }
+            Self::Range(implementation, clauses) => {
+                let joined = clauses
+                    .iter()
+                    .map(Clause::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("{} {}", implementation.display_name(), joined)
+            }
         }
     }
 }
@@ -81,16 +338,22 @@ impl ToString for RequestedVersion {
 impl FromStr for RequestedVersion {
     type Err = Error;
 
-    // XXX Require `python` as a prefix?
     fn from_str(version_string: &str) -> Result<Self> {
         if version_string.is_empty() {
-            Ok(Self::Any)
-        } else if version_string.contains('.') {
-            let exact_version = ExactVersion::from_str(version_string)?;
-            Ok(Self::Exact(exact_version.major, exact_version.minor))
+            return Ok(Self::Any);
+        }
+
+        let (prefix, rest) = split_implementation_prefix(version_string);
+        let implementation = prefix.map_or(Implementation::CPython, Implementation::from_prefix);
+
+        if rest.contains(['>', '<', '=', '~']) {
+            Ok(Self::Range(implementation, parse_range(rest)?))
+        } else if rest.contains('.') {
+            let (major, minor) = parse_major_minor(rest)?;
+            Ok(Self::Exact(implementation, major, minor))
         } else {
-            match version_string.parse::<ComponentSize>() {
-                Ok(number) => Ok(Self::MajorOnly(number)),
+            match rest.parse::<ComponentSize>() {
+                Ok(number) => Ok(Self::MajorOnly(implementation, number)),
                 Err(parse_error) => Err(Error::ParseVersionComponentError(parse_error)),
             }
         }
@@ -99,16 +362,67 @@ impl FromStr for RequestedVersion {
 
 impl RequestedVersion {
     /// Returns the string representing the environment variable for the requested version.
-    pub fn env_var(self) -> Option<String> {
+    pub fn env_var(&self) -> Option<String> {
         match self {
             Self::Any => Some("PY_PYTHON".to_string()),
-            Self::MajorOnly(major) => Some(format!("PY_PYTHON{}", major)),
+            Self::MajorOnly(Implementation::CPython, major) => Some(format!("PY_PYTHON{}", major)),
             _ => None,
         }
     }
+
+    /// Determines the requested version from a script's shebang line, the way the
+    /// Windows launcher picks an interpreter for `py ./script.py`.
+    ///
+    /// Returns `None` if the first line can't be read or isn't a `#!` shebang, and
+    /// `Some(Self::Any)` if the shebang is present but doesn't name a recognized
+    /// Python interpreter (including an empty or whitespace-only shebang).
+    pub fn from_shebang(script: &Path) -> Option<Self> {
+        let file = fs::File::open(script).ok()?;
+        let mut first_line = String::new();
+        BufReader::new(file).read_line(&mut first_line).ok()?;
+
+        let shebang = first_line.trim_end_matches(['\n', '\r']).strip_prefix("#!")?;
+
+        let mut tokens = shebang.split_whitespace();
+        let mut interpreter = match tokens.next() {
+            Some(interpreter) => interpreter,
+            None => return Some(Self::Any), // `#!` alone or whitespace-only.
+        };
+        if Path::new(interpreter).file_name().and_then(|n| n.to_str()) == Some("env") {
+            interpreter = match tokens.find(|arg| !arg.starts_with('-')) {
+                Some(interpreter) => interpreter,
+                None => return Some(Self::Any), // `#!/usr/bin/env` with no trailing token.
+            };
+        }
+
+        let file_name = match Path::new(interpreter).file_name().and_then(|n| n.to_str()) {
+            Some(file_name) => file_name,
+            None => return Some(Self::Any),
+        };
+        Some(Self::from_interpreter_name(file_name))
+    }
+
+    /// Parses an interpreter's file name (e.g. `python3.9` from a shebang) into the
+    /// version it requests, falling back to `Self::Any` when it isn't recognized as
+    /// a Python interpreter.
+    fn from_interpreter_name(file_name: &str) -> Self {
+        for prefix in KNOWN_IMPLEMENTATION_PREFIXES {
+            if let Some(version_part) = file_name.strip_prefix(prefix) {
+                let implementation = Implementation::from_prefix(prefix);
+                if let Ok((major, minor)) = parse_major_minor(version_part) {
+                    return Self::Exact(implementation, major, minor);
+                }
+                if let Ok(major) = version_part.parse::<ComponentSize>() {
+                    return Self::MajorOnly(implementation, major);
+                }
+                break;
+            }
+        }
+        Self::Any
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ExactVersion {
     pub major: ComponentSize,
     pub minor: ComponentSize,
@@ -116,7 +430,7 @@ pub struct ExactVersion {
 
 impl From<ExactVersion> for RequestedVersion {
     fn from(version: ExactVersion) -> Self {
-        Self::Exact(version.major, version.minor)
+        Self::Exact(Implementation::CPython, version.major, version.minor)
     }
 }
 
@@ -130,33 +444,26 @@ impl FromStr for ExactVersion {
     type Err = Error;
 
     fn from_str(version_string: &str) -> Result<Self> {
-        if let Some(dot_index) = version_string.find('.') {
-            let major_str = &version_string[..dot_index];
-            let major = match major_str.parse::<ComponentSize>() {
-                Ok(number) => number,
-                Err(parse_error) => return Err(Error::ParseVersionComponentError(parse_error)),
-            };
-
-            let minor_str = &version_string[dot_index + 1..];
-            match minor_str.parse::<ComponentSize>() {
-                Ok(minor) => Ok(Self { major, minor }),
-                Err(parse_error) => Err(Error::ParseVersionComponentError(parse_error)),
-            }
-        } else {
-            Err(Error::DotMissing)
-        }
+        let (major, minor) = parse_major_minor(version_string)?;
+        Ok(Self { major, minor })
     }
 }
 
 impl ExactVersion {
-    pub fn from_path(path: &Path) -> Result<Self> {
+    /// Parses a `PATH` entry's file name (e.g. `python3.11` or `pypy3.9`)
+    /// into the implementation that provides it and its exact version.
+    pub fn from_path(path: &Path) -> Result<(Implementation, Self)> {
         if let Some(raw_file_name) = path.file_name() {
             if let Some(file_name) = raw_file_name.to_str() {
-                if file_name.len() >= "python3.0".len() && file_name.starts_with("python") {
-                    let version_part = &file_name["python".len()..];
-                    return Self::from_str(version_part);
+                match file_name.find(|c: char| c.is_ascii_digit()) {
+                    Some(split_at) if split_at > 0 => {
+                        let prefix = &file_name[..split_at];
+                        let version_part = &file_name[split_at..];
+                        let (major, minor) = parse_major_minor(version_part)?;
+                        Ok((Implementation::from_prefix(prefix), Self { major, minor }))
+                    }
+                    _ => Err(Error::PathFileNameError),
                 }
-                Err(Error::PathFileNameError)
             } else {
                 Err(Error::FileNameToStrError)
             }
@@ -165,15 +472,16 @@ impl ExactVersion {
         }
     }
 
-    // XXX from_shebang()?
-
-    pub fn supports(&self, requested: RequestedVersion) -> bool {
+    pub fn supports(&self, requested: &RequestedVersion) -> bool {
         match requested {
             RequestedVersion::Any => true,
-            RequestedVersion::MajorOnly(major_version) => self.major == major_version,
-            RequestedVersion::Exact(major_version, minor_version) => {
-                self.major == major_version && self.minor == minor_version
+            RequestedVersion::MajorOnly(_, major_version) => self.major == *major_version,
+            RequestedVersion::Exact(_, major_version, minor_version) => {
+                self.major == *major_version && self.minor == *minor_version
             }
+            RequestedVersion::Range(_, clauses) => clauses
+                .iter()
+                .all(|clause| clause.is_satisfied_by(self.major, self.minor)),
         }
     }
 }
@@ -201,36 +509,150 @@ fn flatten_directories(
 
 fn all_executables_in_paths(
     paths: impl IntoIterator<Item = PathBuf>,
-) -> HashMap<ExactVersion, PathBuf> {
+) -> HashMap<(Implementation, ExactVersion), PathBuf> {
     let mut executables = HashMap::new();
     for path in paths {
-        if let Ok(version) = ExactVersion::from_path(&path) {
-            executables.entry(version).or_insert(path);
+        if let Ok((implementation, version)) = ExactVersion::from_path(&path) {
+            executables.entry((implementation, version)).or_insert(path);
         }
     }
     executables
 }
 
-pub fn all_executables() -> HashMap<ExactVersion, PathBuf> {
+pub fn all_executables() -> HashMap<(Implementation, ExactVersion), PathBuf> {
     let paths = flatten_directories(env_path());
     all_executables_in_paths(paths)
 }
 
+// Fed to a candidate executable's `-c` flag to learn its real version.
+const VERSION_PROBE_SCRIPT: &str = "import sys;print('%d.%d'%sys.version_info[:2])";
+
+/// Runs `path -c <probe script>` and parses the result as an `ExactVersion`,
+/// returning `None` if spawning it, running it, or parsing its output fails.
+fn probe_version(path: &Path) -> Option<ExactVersion> {
+    let output = Command::new(path)
+        .arg("-c")
+        .arg(VERSION_PROBE_SCRIPT)
+        .stdin(Stdio::null()) // Defense in depth: never let a probed child read our stdin.
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    ExactVersion::from_str(stdout.trim()).ok()
+}
+
+/// Guesses the implementation of an executable whose name doesn't encode a
+/// minor version (e.g. `python3`, `pypy`), from whatever alphabetic prefix
+/// precedes its first digit (or its whole name, if it has none).
+fn guess_implementation(path: &Path) -> Implementation {
+    let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+    let prefix_end = file_name
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(file_name.len());
+    Implementation::from_prefix(&file_name[..prefix_end])
+}
+
+/// Whether `path`'s file name is a known implementation name lacking a minor
+/// version, e.g. `python`, `python3`, `pypy`, `graalpy3` — and so is worth the
+/// cost of actually spawning it to learn its version. This deliberately does
+/// *not* match on "anything `ExactVersion::from_path` can't parse", since that
+/// would include every other executable on `PATH` (`ls`, `git`, `vim`, ...).
+fn is_probe_candidate(path: &Path) -> bool {
+    let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+    KNOWN_IMPLEMENTATION_PREFIXES.iter().any(|prefix| {
+        file_name.strip_prefix(prefix).is_some_and(|rest| {
+            rest.is_empty() || (rest.len() == 1 && rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+    })
+}
+
+/// Folds the `PATH` executables that pass `is_probe_candidate` (e.g. bare
+/// `python`/`python3`) into `executables`, by actually running them; other
+/// names whose version can't be read from the file name are not run. A
+/// filename-derived entry already in `executables` always
+/// wins over a probed one, so the cheap fast path is never undone.
+fn probe_unversioned_executables(
+    paths: impl IntoIterator<Item = PathBuf>,
+    executables: &mut HashMap<(Implementation, ExactVersion), PathBuf>,
+) {
+    let mut cache: HashMap<PathBuf, Option<ExactVersion>> = HashMap::new();
+    for path in paths {
+        if !is_probe_candidate(&path) {
+            continue; // Not a Python-shaped name; don't spawn arbitrary PATH entries.
+        }
+        if ExactVersion::from_path(&path).is_ok() {
+            continue; // Already captured cheaply; no need to spawn it.
+        }
+        let cache_key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        let version = *cache
+            .entry(cache_key)
+            .or_insert_with(|| probe_version(&path));
+        if let Some(version) = version {
+            executables
+                .entry((guess_implementation(&path), version))
+                .or_insert(path);
+        }
+    }
+}
+
+/// Like `all_executables()`, but additionally probes candidates such as bare
+/// `python`/`python3` by running them, at the cost of spawning a process per
+/// such candidate.
+pub fn all_executables_with_probing() -> HashMap<(Implementation, ExactVersion), PathBuf> {
+    let paths: Vec<PathBuf> = flatten_directories(env_path()).collect();
+    let mut executables = all_executables_in_paths(paths.iter().cloned());
+    probe_unversioned_executables(paths, &mut executables);
+    executables
+}
+
 fn find_executable_in_hashmap(
-    requested: RequestedVersion,
-    found_executables: &HashMap<ExactVersion, PathBuf>,
+    requested: &RequestedVersion,
+    found_executables: &HashMap<(Implementation, ExactVersion), PathBuf>,
 ) -> Option<PathBuf> {
     let mut iter = found_executables.iter();
     match requested {
-        RequestedVersion::Any => iter.max(),
-        RequestedVersion::MajorOnly(_) => iter.filter(|pair| pair.0.supports(requested)).max(),
-        RequestedVersion::Exact(_, _) => iter.find(|pair| pair.0.supports(requested)),
+        RequestedVersion::Any => iter
+            .filter(|((implementation, _), _)| *implementation == Implementation::CPython)
+            .max_by_key(|((_, version), _)| version),
+        RequestedVersion::MajorOnly(implementation, _) | RequestedVersion::Range(implementation, _) => {
+            iter.filter(|((found_implementation, version), _)| {
+                found_implementation == implementation && version.supports(requested)
+            })
+            .max_by_key(|((_, version), _)| version)
+        }
+        RequestedVersion::Exact(implementation, _, _) => {
+            iter.find(|((found_implementation, version), _)| {
+                found_implementation == implementation && version.supports(requested)
+            })
+        }
     }
     .map(|pair| pair.1.clone())
 }
 
-pub fn find_executable(requested: RequestedVersion) -> Option<PathBuf> {
-    let found_executables = all_executables();
+/// Searches every directory in `paths` for an executable supporting `requested`.
+///
+/// For a `MajorOnly` or `Range` request this keeps scanning every directory
+/// rather than stopping at the first match, so e.g. a `python3` earlier on
+/// `PATH` pointing at an older release can't shadow a newer `python3.x`
+/// found in a later directory (matching uv's "search all of PATH" fix). Ties
+/// on the same version are broken in favor of the earlier `PATH` entry, as
+/// `all_executables_in_paths` already guarantees.
+pub fn find_executable_in_paths(
+    requested: &RequestedVersion,
+    paths: impl IntoIterator<Item = PathBuf>,
+) -> Option<PathBuf> {
+    let found_executables = all_executables_in_paths(flatten_directories(paths));
+    find_executable_in_hashmap(requested, &found_executables)
+}
+
+pub fn find_executable(requested: &RequestedVersion) -> Option<PathBuf> {
+    find_executable_in_paths(requested, env_path())
+}
+
+pub fn find_executable_with_probing(requested: &RequestedVersion) -> Option<PathBuf> {
+    let found_executables = all_executables_with_probing();
     find_executable_in_hashmap(requested, &found_executables)
 }
 
@@ -241,8 +663,18 @@ mod tests {
     #[test]
     fn test_requestedversion_to_string() {
         assert_eq!(RequestedVersion::Any.to_string(), "Python");
-        assert_eq!(RequestedVersion::MajorOnly(3).to_string(), "Python 3");
-        assert_eq!(RequestedVersion::Exact(3, 8).to_string(), "Python 3.8");
+        assert_eq!(
+            RequestedVersion::MajorOnly(Implementation::CPython, 3).to_string(),
+            "Python 3"
+        );
+        assert_eq!(
+            RequestedVersion::Exact(Implementation::CPython, 3, 8).to_string(),
+            "Python 3.8"
+        );
+        assert_eq!(
+            RequestedVersion::Exact(Implementation::PyPy, 3, 9).to_string(),
+            "PyPy 3.9"
+        );
     }
 
     #[test]
@@ -252,20 +684,129 @@ mod tests {
         assert!(RequestedVersion::from_str("h").is_err());
         assert!(RequestedVersion::from_str("3.b").is_err());
         assert!(RequestedVersion::from_str("a.7").is_err());
+        assert!(RequestedVersion::from_str("3.6.5").is_err());
         assert_eq!(RequestedVersion::from_str(""), Ok(RequestedVersion::Any));
         assert_eq!(
             RequestedVersion::from_str("3"),
-            Ok(RequestedVersion::MajorOnly(3))
+            Ok(RequestedVersion::MajorOnly(Implementation::CPython, 3))
         );
         assert_eq!(
             RequestedVersion::from_str("3.8"),
-            Ok(RequestedVersion::Exact(3, 8))
+            Ok(RequestedVersion::Exact(Implementation::CPython, 3, 8))
         );
         assert_eq!(
             RequestedVersion::from_str("42.13"),
-            Ok(RequestedVersion::Exact(42, 13))
+            Ok(RequestedVersion::Exact(Implementation::CPython, 42, 13))
+        );
+    }
+
+    #[test]
+    fn test_requestedversion_from_str_implementation() {
+        assert_eq!(
+            RequestedVersion::from_str("pypy3.9"),
+            Ok(RequestedVersion::Exact(Implementation::PyPy, 3, 9))
+        );
+        assert_eq!(
+            RequestedVersion::from_str("pypy3"),
+            Ok(RequestedVersion::MajorOnly(Implementation::PyPy, 3))
+        );
+        assert_eq!(
+            RequestedVersion::from_str("graalpy3.10"),
+            Ok(RequestedVersion::Exact(Implementation::GraalPy, 3, 10))
+        );
+        assert_eq!(
+            RequestedVersion::from_str("python3.8"),
+            Ok(RequestedVersion::Exact(Implementation::CPython, 3, 8))
+        );
+        assert_eq!(
+            RequestedVersion::from_str("jython2.7"),
+            Ok(RequestedVersion::Exact(
+                Implementation::Other("jython".to_string()),
+                2,
+                7
+            ))
+        );
+    }
+
+    #[test]
+    fn test_requestedversion_from_str_range() {
+        assert_eq!(
+            RequestedVersion::from_str(">=3.11"),
+            Ok(RequestedVersion::Range(
+                Implementation::CPython,
+                vec![Clause {
+                    operator: Operator::GreaterThanOrEqual,
+                    major: 3,
+                    minor: Some(11),
+                }]
+            ))
+        );
+        assert_eq!(
+            RequestedVersion::from_str(">=3.10,<3.13"),
+            Ok(RequestedVersion::Range(
+                Implementation::CPython,
+                vec![
+                    Clause {
+                        operator: Operator::GreaterThanOrEqual,
+                        major: 3,
+                        minor: Some(10),
+                    },
+                    Clause {
+                        operator: Operator::LessThan,
+                        major: 3,
+                        minor: Some(13),
+                    },
+                ]
+            ))
+        );
+        assert_eq!(
+            RequestedVersion::from_str("~=3.9"),
+            Ok(RequestedVersion::Range(
+                Implementation::CPython,
+                vec![
+                    Clause {
+                        operator: Operator::GreaterThanOrEqual,
+                        major: 3,
+                        minor: Some(9),
+                    },
+                    Clause {
+                        operator: Operator::LessThan,
+                        major: 4,
+                        minor: None,
+                    },
+                ]
+            ))
+        );
+        assert_eq!(
+            RequestedVersion::from_str("==3.*"),
+            Ok(RequestedVersion::Range(
+                Implementation::CPython,
+                vec![Clause {
+                    operator: Operator::Equal,
+                    major: 3,
+                    minor: None,
+                }]
+            ))
+        );
+        assert_eq!(
+            RequestedVersion::from_str("pypy>=3.9"),
+            Ok(RequestedVersion::Range(
+                Implementation::PyPy,
+                vec![Clause {
+                    operator: Operator::GreaterThanOrEqual,
+                    major: 3,
+                    minor: Some(9),
+                }]
+            ))
+        );
+        assert_eq!(
+            RequestedVersion::from_str("~=3"),
+            Err(Error::CompatibleReleaseMissingMinor)
+        );
+        assert_eq!(
+            RequestedVersion::from_str("=3.9"),
+            Err(Error::UnknownRangeOperator)
         );
-        assert!(RequestedVersion::from_str("3.6.5").is_err());
     }
 
     #[test]
@@ -275,14 +816,133 @@ mod tests {
             Some("PY_PYTHON".to_string())
         );
         assert_eq!(
-            RequestedVersion::MajorOnly(3).env_var(),
+            RequestedVersion::MajorOnly(Implementation::CPython, 3).env_var(),
             Some("PY_PYTHON3".to_string())
         );
         assert_eq!(
-            RequestedVersion::MajorOnly(42).env_var(),
+            RequestedVersion::MajorOnly(Implementation::CPython, 42).env_var(),
             Some("PY_PYTHON42".to_string())
         );
-        assert!(RequestedVersion::Exact(42, 13).env_var().is_none());
+        assert!(RequestedVersion::Exact(Implementation::CPython, 42, 13)
+            .env_var()
+            .is_none());
+        assert!(RequestedVersion::MajorOnly(Implementation::PyPy, 3)
+            .env_var()
+            .is_none());
+        assert!(RequestedVersion::from_str(">=3.10")
+            .unwrap()
+            .env_var()
+            .is_none());
+    }
+
+    fn write_temp_script(name: &str, contents: &str) -> PathBuf {
+        let path =
+            env::temp_dir().join(format!("python-launcher-test-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).expect("failed to write temp script");
+        path
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_direct() {
+        let path = write_temp_script("direct", "#!/usr/bin/python3.8\nprint('hi')\n");
+        assert_eq!(
+            RequestedVersion::from_shebang(&path),
+            Some(RequestedVersion::Exact(Implementation::CPython, 3, 8))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_env() {
+        let path = write_temp_script("env", "#!/usr/bin/env python3.9\n");
+        assert_eq!(
+            RequestedVersion::from_shebang(&path),
+            Some(RequestedVersion::Exact(Implementation::CPython, 3, 9))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_env_with_options() {
+        let path = write_temp_script("env_opts", "#!/usr/bin/env -S python3.9\n");
+        assert_eq!(
+            RequestedVersion::from_shebang(&path),
+            Some(RequestedVersion::Exact(Implementation::CPython, 3, 9))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_pypy() {
+        let path = write_temp_script("pypy", "#!/usr/local/bin/pypy3\n");
+        assert_eq!(
+            RequestedVersion::from_shebang(&path),
+            Some(RequestedVersion::MajorOnly(Implementation::PyPy, 3))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_non_python() {
+        let path = write_temp_script("sh", "#!/bin/sh\n");
+        assert_eq!(
+            RequestedVersion::from_shebang(&path),
+            Some(RequestedVersion::Any)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_no_shebang() {
+        let path = write_temp_script("noshebang", "print('hi')\n");
+        assert_eq!(RequestedVersion::from_shebang(&path), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_empty_file() {
+        let path = write_temp_script("empty", "");
+        assert_eq!(RequestedVersion::from_shebang(&path), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_missing_file() {
+        let path = env::temp_dir().join(format!(
+            "python-launcher-test-{}-missing",
+            std::process::id()
+        ));
+        assert_eq!(RequestedVersion::from_shebang(&path), None);
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_empty_shebang() {
+        let path = write_temp_script("empty_shebang", "#!\n");
+        assert_eq!(
+            RequestedVersion::from_shebang(&path),
+            Some(RequestedVersion::Any)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_whitespace_shebang() {
+        let path = write_temp_script("whitespace_shebang", "#!   \n");
+        assert_eq!(
+            RequestedVersion::from_shebang(&path),
+            Some(RequestedVersion::Any)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_requestedversion_from_shebang_env_no_interpreter() {
+        let path = write_temp_script("env_bare", "#!/usr/bin/env\n");
+        assert_eq!(
+            RequestedVersion::from_shebang(&path),
+            Some(RequestedVersion::Any)
+        );
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
@@ -292,7 +952,7 @@ mod tests {
                 major: 42,
                 minor: 13
             }),
-            RequestedVersion::Exact(42, 13)
+            RequestedVersion::Exact(Implementation::CPython, 42, 13)
         );
     }
 
@@ -361,20 +1021,31 @@ mod tests {
         );
         assert_eq!(
             ExactVersion::from_path(&PathBuf::from("/python3")),
-            Err(Error::PathFileNameError)
+            Err(Error::DotMissing)
         );
         assert_eq!(
-            ExactVersion::from_path(&PathBuf::from("/pythonX.Y")),
-            Err(Error::ParseVersionComponentError(
-                "X".parse::<ComponentSize>().unwrap_err()
+            ExactVersion::from_path(&PathBuf::from("/python42.13")),
+            Ok((
+                Implementation::CPython,
+                ExactVersion {
+                    major: 42,
+                    minor: 13
+                }
             ))
         );
         assert_eq!(
-            ExactVersion::from_path(&PathBuf::from("/python42.13")),
-            Ok(ExactVersion {
-                major: 42,
-                minor: 13
-            })
+            ExactVersion::from_path(&PathBuf::from("/usr/bin/pypy3.9")),
+            Ok((Implementation::PyPy, ExactVersion { major: 3, minor: 9 }))
+        );
+        assert_eq!(
+            ExactVersion::from_path(&PathBuf::from("/usr/local/bin/graalpy3.10")),
+            Ok((
+                Implementation::GraalPy,
+                ExactVersion {
+                    major: 3,
+                    minor: 10
+                }
+            ))
         );
     }
 
@@ -382,14 +1053,41 @@ mod tests {
     fn test_exactversion_supports() {
         let example = ExactVersion { major: 3, minor: 7 };
 
-        assert!(example.supports(RequestedVersion::Any));
+        assert!(example.supports(&RequestedVersion::Any));
+
+        assert!(!example.supports(&RequestedVersion::MajorOnly(Implementation::CPython, 2)));
+        assert!(example.supports(&RequestedVersion::MajorOnly(Implementation::CPython, 3)));
+
+        assert!(!example.supports(&RequestedVersion::Exact(Implementation::CPython, 2, 7)));
+        assert!(!example.supports(&RequestedVersion::Exact(Implementation::CPython, 3, 6)));
+        assert!(example.supports(&RequestedVersion::Exact(Implementation::CPython, 3, 7)));
+    }
+
+    #[test]
+    fn test_exactversion_supports_range() {
+        let py36 = ExactVersion { major: 3, minor: 6 };
+        let py37 = ExactVersion { major: 3, minor: 7 };
+        let py312 = ExactVersion {
+            major: 3,
+            minor: 12,
+        };
+        let py4 = ExactVersion { major: 4, minor: 0 };
+
+        let range = RequestedVersion::from_str(">=3.10,<3.13").unwrap();
+        assert!(!py36.supports(&range));
+        assert!(!py37.supports(&range));
+        assert!(py312.supports(&range));
+        assert!(!py4.supports(&range));
 
-        assert!(!example.supports(RequestedVersion::MajorOnly(2)));
-        assert!(example.supports(RequestedVersion::MajorOnly(3)));
+        let compatible = RequestedVersion::from_str("~=3.9").unwrap();
+        assert!(!py36.supports(&compatible));
+        assert!(!py37.supports(&compatible));
+        assert!(py312.supports(&compatible));
+        assert!(!py4.supports(&compatible));
 
-        assert!(!example.supports(RequestedVersion::Exact(2, 7)));
-        assert!(!example.supports(RequestedVersion::Exact(3, 6)));
-        assert!(example.supports(RequestedVersion::Exact(3, 7)));
+        let wildcard = RequestedVersion::from_str("==3.*").unwrap();
+        assert!(py37.supports(&wildcard));
+        assert!(!py4.supports(&wildcard));
     }
 
     #[test]
@@ -398,67 +1096,291 @@ mod tests {
         let python36_dir1_path = PathBuf::from("/dir1/python3.6");
         let python36_dir2_path = PathBuf::from("/dir2/python3.6");
         let python37_path = PathBuf::from("/dir2/python3.7");
+        let pypy39_path = PathBuf::from("/dir2/pypy3.9");
         let files = vec![
             python27_path.to_owned(),
             python36_dir1_path.to_owned(),
             python36_dir2_path,
             python37_path.to_owned(),
+            pypy39_path.to_owned(),
         ];
 
-        let executables = all_executables_in_paths(files.into_iter());
-        assert_eq!(executables.len(), 3);
+        let executables = all_executables_in_paths(files);
+        assert_eq!(executables.len(), 4);
 
-        let python27_version = ExactVersion { major: 2, minor: 7 };
-        assert!(executables.contains_key(&python27_version));
-        assert_eq!(executables.get(&python27_version), Some(&python27_path));
+        let python27_key = (Implementation::CPython, ExactVersion { major: 2, minor: 7 });
+        assert_eq!(executables.get(&python27_key), Some(&python27_path));
 
-        let python36_version = ExactVersion { major: 3, minor: 6 };
-        assert!(executables.contains_key(&python27_version));
+        let python36_key = (Implementation::CPython, ExactVersion { major: 3, minor: 6 });
         assert_eq!(
-            executables.get(&python36_version),
+            executables.get(&python36_key),
             Some(&python36_dir1_path)
         );
 
-        let python37_version = ExactVersion { major: 3, minor: 7 };
-        assert!(executables.contains_key(&python37_version));
-        assert_eq!(executables.get(&python37_version), Some(&python37_path));
+        let python37_key = (Implementation::CPython, ExactVersion { major: 3, minor: 7 });
+        assert_eq!(executables.get(&python37_key), Some(&python37_path));
+
+        let pypy39_key = (Implementation::PyPy, ExactVersion { major: 3, minor: 9 });
+        assert_eq!(executables.get(&pypy39_key), Some(&pypy39_path));
+    }
+
+    // A fake "interpreter": a shell script that ignores its arguments and just
+    // prints a version, so probing doesn't depend on a real Python being
+    // installed wherever the tests run. Lives in its own directory so its
+    // file name (e.g. `python3`) stays exactly what the test asks for.
+    fn write_fake_interpreter(test_name: &str, file_name: &str, version: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!(
+            "python-launcher-test-{}-{}",
+            std::process::id(),
+            test_name
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(file_name);
+        std::fs::write(&path, format!("#!/bin/sh\necho {}\n", version)).unwrap();
+
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_mode(0o755);
+        std::fs::set_permissions(&path, permissions).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_probe_version() {
+        let path = write_fake_interpreter("probe-ok", "python3", "3.11");
+        assert_eq!(
+            probe_version(&path),
+            Some(ExactVersion {
+                major: 3,
+                minor: 11
+            })
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let garbage_path = write_fake_interpreter("probe-garbage", "python3", "not a version");
+        assert_eq!(probe_version(&garbage_path), None);
+        std::fs::remove_dir_all(garbage_path.parent().unwrap()).unwrap();
+
+        assert_eq!(probe_version(&PathBuf::from("/does/not/exist")), None);
+    }
+
+    #[test]
+    fn test_guess_implementation() {
+        assert_eq!(
+            guess_implementation(&PathBuf::from("/usr/bin/python3")),
+            Implementation::CPython
+        );
+        assert_eq!(
+            guess_implementation(&PathBuf::from("/usr/bin/python")),
+            Implementation::CPython
+        );
+        assert_eq!(
+            guess_implementation(&PathBuf::from("/usr/bin/pypy3")),
+            Implementation::PyPy
+        );
+        assert_eq!(
+            guess_implementation(&PathBuf::from("/usr/bin/pypy")),
+            Implementation::PyPy
+        );
+    }
+
+    #[test]
+    fn test_is_probe_candidate() {
+        assert!(is_probe_candidate(&PathBuf::from("/usr/bin/python")));
+        assert!(is_probe_candidate(&PathBuf::from("/usr/bin/python3")));
+        assert!(is_probe_candidate(&PathBuf::from("/usr/bin/pypy")));
+        assert!(is_probe_candidate(&PathBuf::from("/usr/bin/pypy3")));
+        assert!(is_probe_candidate(&PathBuf::from("/usr/bin/graalpy3")));
+
+        // Already fully versioned; the cheap `from_path` path handles these.
+        assert!(!is_probe_candidate(&PathBuf::from("/usr/bin/python3.11")));
+        // Not a known implementation name at all.
+        assert!(!is_probe_candidate(&PathBuf::from("/usr/bin/ls")));
+        assert!(!is_probe_candidate(&PathBuf::from("/usr/bin/vim")));
+        assert!(!is_probe_candidate(&PathBuf::from("/usr/bin/python-config")));
+    }
+
+    #[test]
+    fn test_probe_unversioned_executables_skips_non_python_executables() {
+        // Even though this "looks like" it would probe successfully, it must
+        // never be spawned: its name isn't a known Python implementation.
+        let ls_path = write_fake_interpreter("probe-nonpython", "ls", "9.9");
+
+        let mut executables = HashMap::new();
+        probe_unversioned_executables(vec![ls_path.clone()], &mut executables);
+
+        assert!(executables.is_empty());
+        std::fs::remove_dir_all(ls_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_probe_unversioned_executables() {
+        let python3_path = write_fake_interpreter("probe-dir", "python3", "3.12");
+        let versioned_path = PathBuf::from("/dir/python3.6"); // Should be skipped: cheap path wins.
+
+        let mut executables = HashMap::new();
+        executables.insert(
+            (Implementation::CPython, ExactVersion { major: 3, minor: 6 }),
+            versioned_path.clone(),
+        );
+
+        probe_unversioned_executables(
+            vec![python3_path.clone(), versioned_path.clone()],
+            &mut executables,
+        );
+
+        assert_eq!(
+            executables.get(&(Implementation::CPython, ExactVersion { major: 3, minor: 12 })),
+            Some(&python3_path)
+        );
+        // The filename-derived entry for 3.6 must survive untouched.
+        assert_eq!(
+            executables.get(&(Implementation::CPython, ExactVersion { major: 3, minor: 6 })),
+            Some(&versioned_path)
+        );
+
+        std::fs::remove_dir_all(python3_path.parent().unwrap()).unwrap();
     }
 
     #[test]
     fn test_find_executable_in_hashmap() {
         let mut executables = HashMap::new();
         assert_eq!(
-            find_executable_in_hashmap(RequestedVersion::Any, &executables),
+            find_executable_in_hashmap(&RequestedVersion::Any, &executables),
             None
         );
 
         let python36_path = PathBuf::from("/python3.6");
-        executables.insert(ExactVersion { major: 3, minor: 6 }, python36_path.clone());
+        executables.insert(
+            (Implementation::CPython, ExactVersion { major: 3, minor: 6 }),
+            python36_path.clone(),
+        );
 
         let python37_path = PathBuf::from("/python3.7");
-        executables.insert(ExactVersion { major: 3, minor: 7 }, python37_path.clone());
+        executables.insert(
+            (Implementation::CPython, ExactVersion { major: 3, minor: 7 }),
+            python37_path.clone(),
+        );
+
+        let pypy39_path = PathBuf::from("/pypy3.9");
+        executables.insert(
+            (Implementation::PyPy, ExactVersion { major: 3, minor: 9 }),
+            pypy39_path.clone(),
+        );
 
         assert_eq!(
-            find_executable_in_hashmap(RequestedVersion::Any, &executables),
+            find_executable_in_hashmap(&RequestedVersion::Any, &executables),
             Some(python37_path.clone())
         );
 
         assert_eq!(
-            find_executable_in_hashmap(RequestedVersion::MajorOnly(42), &executables),
+            find_executable_in_hashmap(
+                &RequestedVersion::MajorOnly(Implementation::CPython, 42),
+                &executables
+            ),
             None
         );
         assert_eq!(
-            find_executable_in_hashmap(RequestedVersion::MajorOnly(3), &executables),
-            Some(python37_path)
+            find_executable_in_hashmap(
+                &RequestedVersion::MajorOnly(Implementation::CPython, 3),
+                &executables
+            ),
+            Some(python37_path.clone())
+        );
+        assert_eq!(
+            find_executable_in_hashmap(
+                &RequestedVersion::MajorOnly(Implementation::PyPy, 3),
+                &executables
+            ),
+            Some(pypy39_path.clone())
         );
 
         assert_eq!(
-            find_executable_in_hashmap(RequestedVersion::Exact(3, 8), &executables),
+            find_executable_in_hashmap(
+                &RequestedVersion::Exact(Implementation::CPython, 3, 8),
+                &executables
+            ),
             None
         );
         assert_eq!(
-            find_executable_in_hashmap(RequestedVersion::Exact(3, 6), &executables),
+            find_executable_in_hashmap(
+                &RequestedVersion::Exact(Implementation::CPython, 3, 6),
+                &executables
+            ),
             Some(python36_path)
         );
+        assert_eq!(
+            find_executable_in_hashmap(
+                &RequestedVersion::Exact(Implementation::PyPy, 3, 9),
+                &executables
+            ),
+            Some(pypy39_path.clone())
+        );
+        assert_eq!(
+            find_executable_in_hashmap(
+                &RequestedVersion::Exact(Implementation::PyPy, 3, 8),
+                &executables
+            ),
+            None
+        );
+
+        let range = RequestedVersion::from_str(">=3.7").unwrap();
+        assert_eq!(
+            find_executable_in_hashmap(&range, &executables),
+            Some(python37_path)
+        );
+
+        let pypy_range = RequestedVersion::from_str("pypy>=3.8").unwrap();
+        assert_eq!(
+            find_executable_in_hashmap(&pypy_range, &executables),
+            Some(pypy39_path)
+        );
+    }
+
+    #[test]
+    fn test_find_executable_in_paths() {
+        let base = env::temp_dir().join(format!(
+            "python-launcher-test-{}-find-executable-in-paths",
+            std::process::id()
+        ));
+        let early_dir = base.join("early");
+        let late_dir = base.join("late");
+        fs::create_dir_all(&early_dir).unwrap();
+        fs::create_dir_all(&late_dir).unwrap();
+
+        // An older python3.6 lives early on PATH; a newer python3.9 only
+        // shows up in a later directory. Scanning must
+        // not stop at the first directory that satisfies the request.
+        let early_python36 = early_dir.join("python3.6");
+        fs::write(&early_python36, "").unwrap();
+        let late_python39 = late_dir.join("python3.9");
+        fs::write(&late_python39, "").unwrap();
+
+        let paths = vec![early_dir.clone(), late_dir.clone()];
+        let major_only = RequestedVersion::MajorOnly(Implementation::CPython, 3);
+        assert_eq!(
+            find_executable_in_paths(&major_only, paths.clone()),
+            Some(late_python39)
+        );
+
+        let range = RequestedVersion::from_str(">=3.7").unwrap();
+        assert_eq!(find_executable_in_paths(&range, paths), Some(late_dir.join("python3.9")));
+
+        // Ties on the same version are broken in favor of the earlier directory.
+        let tie_dir_a = base.join("tie_a");
+        let tie_dir_b = base.join("tie_b");
+        fs::create_dir_all(&tie_dir_a).unwrap();
+        fs::create_dir_all(&tie_dir_b).unwrap();
+        let tie_a_python39 = tie_dir_a.join("python3.9");
+        fs::write(&tie_a_python39, "").unwrap();
+        fs::write(tie_dir_b.join("python3.9"), "").unwrap();
+
+        assert_eq!(
+            find_executable_in_paths(&major_only, vec![tie_dir_a, tie_dir_b]),
+            Some(tie_a_python39)
+        );
+
+        fs::remove_dir_all(&base).unwrap();
     }
 }